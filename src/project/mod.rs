@@ -4,9 +4,11 @@ use crate::{
         LoadProjectError, SetSourceDirError, SyncFilesError,
     },
     helper,
-    project_config::{write_conf, Directory},
+    project_config::{build_tree, merge_file_state, write_conf, ConfigFormat, Directory, FollowSymlinks},
     Language,
 };
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
 use crate::project_config::ProjectConfig;
@@ -20,21 +22,42 @@ pub struct Project {
     config: ProjectConfig,
 }
 
+#[derive(Debug, Clone, Default)]
+/// Summary of the changes a [`Project::sync_files`] call made to the target language
+/// directories.
+pub struct SyncReport {
+    /// Files (relative to the source root) copied into a target directory.
+    pub copied: Vec<PathBuf>,
+    /// Previously copied files removed because their source counterpart no longer exists.
+    pub removed: Vec<PathBuf>,
+    /// Translated files left untouched because they hold human translations.
+    pub kept_translations: Vec<PathBuf>,
+    /// Removed orphans that are likely renames: `.0` is the removed path, `.1` is a source
+    /// path that appeared in its place, matched by basename. A suggestion only — the file at
+    /// `.1` is copied like any other new file, nothing is done with this beyond reporting it.
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
 /// Initialize project for translation
-pub fn init(name: &str, path: PathBuf) -> Result<(), InitProjectError> {
+pub fn init(name: &str, path: PathBuf, format: ConfigFormat) -> Result<(), InitProjectError> {
     if !path.is_dir() {
         return Err(InitProjectError::InvalidPath);
     }
     let path = std::fs::canonicalize(path).map_err(|_| InitProjectError::InvalidPath)?;
 
-    let conf = crate::project_config::init(name, path)?;
+    crate::project_config::init(name, path, format)?;
 
     Ok(())
 }
 
-/// Load project from the given path (even if the path is a child of the project directory)
+/// Load project from the given path (even if the path is a child of the project directory),
+/// trying every known [`ConfigFormat`] filename so projects initialized with either backend
+/// can be found.
 pub fn load(path: PathBuf) -> Result<Project, LoadProjectError> {
-    let conf_file_path = match helper::find_file_upwards(path, "trans_conf.json") {
+    let conf_file_path = [ConfigFormat::Json, ConfigFormat::Bincode]
+        .iter()
+        .find_map(|format| helper::find_file_upwards(path.clone(), format.filename()));
+    let conf_file_path = match conf_file_path {
         None => return Err(LoadProjectError::NoConfig),
         Some(r) => r,
     };
@@ -70,7 +93,7 @@ impl Project {
     }
     /// returns the path to the config file
     fn get_config_file_path(&self) -> PathBuf {
-        self.get_root_path().join("trans_conf.json")
+        self.get_root_path().join(self.config.get_format().filename())
     }
 
     /// returns source language in an option or None if the source directory with a language isn't set
@@ -147,41 +170,133 @@ impl Project {
         Ok(())
     }
 
-    // TODO: add result
-    pub fn sync_files(&mut self) -> Result<(), SyncFilesError> {
-        let src_lang = self.get_src_lang().ok_or(SyncFilesError::NoSourceLang)?;
+    /// Computes, without modifying the filesystem, the ordered list of
+    /// operations a [`Project::sync_files`] call would perform against the target language
+    /// directories: directories to create, files to copy, translatable files to leave alone,
+    /// and orphaned copies to remove.
+    ///
+    /// Rescans the source and target directories from disk itself (into local trees, never
+    /// writing them back into the project's config) so the preview is accurate even when
+    /// called standalone, without a prior [`Project::sync_files`] call to refresh them.
+    pub fn plan_sync(&self) -> Result<SyncPlan, SyncFilesError> {
         let conf = self.get_config_as_ref();
         let lang_dirs = conf.get_lang_dirs_as_ref();
         if lang_dirs.is_empty() {
             return Err(SyncFilesError::NoTransLangs);
         }
+        let src_dir = conf
+            .get_src_dir_as_ref()
+            .as_ref()
+            .ok_or(SyncFilesError::NoSourceLang)?;
+        let cached_src_structure = src_dir.get_dir_as_ref();
+        let src_root = cached_src_structure.get_path();
+        let (mut src_structure, _cycles) = build_tree(
+            &src_root,
+            conf.get_ignore_patterns_as_ref(),
+            conf.get_follow_symlinks(),
+            Some(cached_src_structure),
+        )
+        .map_err(SyncFilesError::BuildingConfigError)?;
+        merge_file_state(cached_src_structure, &mut src_structure);
+        let translatable_paths = conf.get_translatable_paths_as_ref();
+
+        let mut ops = Vec::new();
+        let mut renamed = Vec::new();
+        for lang_dir in lang_dirs {
+            let to_root = lang_dir.get_dir_as_ref().get_path();
+            let (to_structure, _cycles) = build_tree(
+                &to_root,
+                conf.get_ignore_patterns_as_ref(),
+                conf.get_follow_symlinks(),
+                Some(lang_dir.get_dir_as_ref()),
+            )
+            .map_err(SyncFilesError::BuildingConfigError)?;
+            plan_dir(&src_root, &src_structure, &to_root, &to_structure, &mut ops);
+            plan_orphans(
+                &src_root,
+                &src_structure,
+                &to_root,
+                &to_structure,
+                translatable_paths,
+                &mut ops,
+                &mut renamed,
+            );
+        }
+        Ok(SyncPlan { ops, renamed })
+    }
 
-        let lang_dirs_names: Vec<String> = lang_dirs
-            .iter()
-            .map(|e| e.get_dir_as_ref().get_dir_name())
-            .collect();
-
-        let src_dir = conf.get_src_dir_as_ref();
-        let src_dir_name = if let Some(l_dir) = src_dir {
-            l_dir.get_dir_as_ref().get_dir_name()
-        } else {
-            panic!("impossible case")
-        };
+    /// Copies untranslatable source files into every target language directory and removes
+    /// previously copied files whose source counterpart has since been deleted or renamed,
+    /// never touching files that hold human translations. Returns a [`SyncReport`] describing
+    /// exactly what changed.
+    pub fn sync_files(&mut self) -> Result<SyncReport, SyncFilesError> {
+        let _src_lang = self.get_src_lang().ok_or(SyncFilesError::NoSourceLang)?;
+        self.config
+            .refresh_src_dir()
+            .map_err(SyncFilesError::BuildingConfigError)?;
 
-        let lang_src_dir = src_dir.clone().unwrap();
-        let src_dir = lang_src_dir.get_dir_as_ref();
+        let plan = self.plan_sync()?;
+        let report = self.apply_sync_plan(&plan)?;
 
-        // copy files
-        for d_name in lang_dirs_names {
-            copy_untranslatable_files(&self.get_root_path(), &src_dir_name, &d_name, src_dir)
-                .map_err(SyncFilesError::CopyError)?;
-        }
         self.config
             .analyze_lang_dirs()
             .map_err(SyncFilesError::BuildingConfigError)?;
         write_conf(self.get_config_file_path(), &self.config)
             .map_err(SyncFilesError::ConfigWritingError)?;
-        Ok(())
+        Ok(report)
+    }
+
+    /// Executes a previously computed [`SyncPlan`] against the filesystem, returning a
+    /// [`SyncReport`] of what actually happened.
+    fn apply_sync_plan(&self, plan: &SyncPlan) -> Result<SyncReport, SyncFilesError> {
+        let mut report = SyncReport {
+            renamed: plan.renamed.clone(),
+            ..Default::default()
+        };
+        for op in &plan.ops {
+            match op {
+                SyncOp::CreateDir(path) => {
+                    if !path.exists() {
+                        std::fs::create_dir(path)
+                            .map_err(|e| SyncFilesError::CopyError(CopyFileDirError::IoError(e)))?;
+                    }
+                }
+                SyncOp::CopyFile { from, to } => {
+                    if std::fs::copy(from, to).is_ok() {
+                        report.copied.push(self.strip_known_root(from));
+                    }
+                }
+                SyncOp::SkipTranslatable(path) => {
+                    report.kept_translations.push(self.strip_known_root(path));
+                }
+                SyncOp::RemoveOrphan(path) => {
+                    if std::fs::remove_file(path).is_ok() {
+                        report.removed.push(self.strip_known_root(path));
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Strips whichever of the source or target language directory roots `path` falls under,
+    /// so [`SyncReport`] entries read as paths relative to a project directory rather than
+    /// absolute filesystem paths.
+    fn strip_known_root(&self, path: &Path) -> PathBuf {
+        let conf = self.get_config_as_ref();
+        if let Some(src_dir) = conf.get_src_dir_as_ref() {
+            let root = src_dir.get_dir_as_ref().get_path();
+            if let Ok(relative) = path.strip_prefix(&root) {
+                return relative.to_path_buf();
+            }
+        }
+        for lang_dir in conf.get_lang_dirs_as_ref() {
+            let root = lang_dir.get_dir_as_ref().get_path();
+            if let Ok(relative) = path.strip_prefix(&root) {
+                return relative.to_path_buf();
+            }
+        }
+        path.to_path_buf()
     }
 
     /// Makes the file by given path translatable (for the source directory)
@@ -207,49 +322,368 @@ impl Project {
             .map_err(AddTranslatableFileError::ConfigWritingError)?;
         Ok(())
     }
+
+    /// Makes every source file matching `pattern` (e.g. `docs/**/*.md`) translatable,
+    /// returning the number of files affected.
+    pub fn make_translatable_glob(&mut self, pattern: &str) -> Result<usize, AddTranslatableFileError> {
+        let count = self.config.make_translatable_glob(pattern)?;
+        write_conf(self.get_config_file_path(), &self.config)
+            .map_err(AddTranslatableFileError::ConfigWritingError)?;
+        Ok(count)
+    }
+
+    /// Makes every source file matching `pattern` untranslatable, returning the number of
+    /// files affected.
+    pub fn make_untranslatable_glob(&mut self, pattern: &str) -> Result<usize, AddTranslatableFileError> {
+        let count = self.config.make_untranslatable_glob(pattern)?;
+        write_conf(self.get_config_file_path(), &self.config)
+            .map_err(AddTranslatableFileError::ConfigWritingError)?;
+        Ok(count)
+    }
+
+    /// Makes every file under `dir_path` (relative to the source directory) translatable,
+    /// returning the number of files affected.
+    pub fn make_directory_translatable(&mut self, dir_path: &Path) -> Result<usize, AddTranslatableFileError> {
+        let count = self.config.make_directory_translatable(dir_path)?;
+        write_conf(self.get_config_file_path(), &self.config)
+            .map_err(AddTranslatableFileError::ConfigWritingError)?;
+        Ok(count)
+    }
+
+    /// Replaces the ignore patterns used when building project trees and immediately
+    /// re-analyzes the source and target language directories so the change takes effect.
+    pub fn set_ignore_patterns(&mut self, patterns: Vec<String>) -> Result<(), SetSourceDirError> {
+        self.config.set_ignore_patterns(patterns);
+        self.config
+            .refresh_src_dir()
+            .map_err(SetSourceDirError::AnalyzeDirError)?;
+        self.config
+            .analyze_lang_dirs()
+            .map_err(SetSourceDirError::AnalyzeDirError)?;
+        let _ = write_conf(self.get_config_file_path(), &self.config);
+        Ok(())
+    }
+
+    /// Changes whether project trees follow symlinks, re-analyzing the source and target
+    /// language directories so the change takes effect immediately.
+    pub fn set_follow_symlinks(&mut self, mode: FollowSymlinks) -> Result<(), SetSourceDirError> {
+        self.config.set_follow_symlinks(mode);
+        self.config
+            .refresh_src_dir()
+            .map_err(SetSourceDirError::AnalyzeDirError)?;
+        self.config
+            .analyze_lang_dirs()
+            .map_err(SetSourceDirError::AnalyzeDirError)?;
+        let _ = write_conf(self.get_config_file_path(), &self.config);
+        Ok(())
+    }
+
+    /// Returns the symlinks that were skipped because they formed a cycle during the most
+    /// recent tree analysis, so callers can warn the user instead of silently dropping them.
+    pub fn symlink_cycles(&self) -> &Vec<PathBuf> {
+        self.config.get_symlink_cycles()
+    }
+
+    /// Returns the paths (relative to the source directory) of translatable files whose
+    /// source content has changed since they were marked translatable, i.e. whose existing
+    /// translation is now out of date.
+    pub fn outdated_translations(&self) -> Vec<PathBuf> {
+        let conf = self.get_config_as_ref();
+        let src_dir = match conf.get_src_dir_as_ref() {
+            Some(r) => r,
+            None => return Vec::new(),
+        };
+        let root = src_dir.get_dir_as_ref().get_path();
+        let mut out = Vec::new();
+        collect_stale(src_dir.get_dir_as_ref(), &root, &mut out);
+        out
+    }
 }
 
-pub fn copy_untranslatable_files(
-    root_path: &Path,
-    from_name: &str,
-    to_name: &str,
-    from_structure: &Directory,
-) -> Result<(), CopyFileDirError> {
-    let from_dir = root_path.clone().join(from_name);
-    let to_dir = root_path.clone().join(to_name);
-    copy_untranslatable_files_rec(&from_dir, &to_dir, from_structure)
+fn collect_stale(dir: &Directory, root: &Path, out: &mut Vec<PathBuf>) {
+    for file in dir.get_files_as_ref() {
+        if file.is_translatable() && file.is_stale() {
+            if let Ok(relative) = file.get_path().strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+        }
+    }
+    for sub_dir in dir.get_dirs_as_ref() {
+        collect_stale(sub_dir, root, out);
+    }
 }
 
-fn copy_untranslatable_files_rec(
-    from_dir: &Path,
-    to_dir: &Path,
-    dir: &Directory,
-) -> Result<(), CopyFileDirError> {
+#[derive(Debug, Clone)]
+/// A single filesystem operation computed by [`Project::plan_sync`].
+pub enum SyncOp {
+    /// Create this directory in a target language directory.
+    CreateDir(PathBuf),
+    /// Copy the source file at `from` to `to`.
+    CopyFile { from: PathBuf, to: PathBuf },
+    /// Leave this source file untouched: it is marked translatable and may already hold a
+    /// human translation in the target directories.
+    SkipTranslatable(PathBuf),
+    /// Remove this previously copied file because its source counterpart no longer exists.
+    RemoveOrphan(PathBuf),
+}
+
+#[derive(Debug, Clone, Default)]
+/// An ordered list of operations [`Project::sync_files`] would perform, computed entirely in
+/// memory by [`Project::plan_sync`] so callers can preview a sync before anything touches disk.
+pub struct SyncPlan {
+    pub ops: Vec<SyncOp>,
+    /// Suggested renames detected while planning orphan removal, see [`SyncReport::renamed`].
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+/// Builds the `CreateDir`/`CopyFile`/`SkipTranslatable` operations for one language directory
+/// by walking the source `Directory` tree and projecting it onto `to_dir`.
+fn plan_dir(from_dir: &Path, dir: &Directory, to_dir: &Path, to_structure: &Directory, ops: &mut Vec<SyncOp>) {
     for file in dir.get_files_as_ref() {
+        let full_path = file.get_path();
+        let relative_path = match full_path.strip_prefix(from_dir) {
+            Ok(r) => r.to_path_buf(),
+            Err(_) => continue,
+        };
+
         if file.is_translatable() {
+            ops.push(SyncOp::SkipTranslatable(full_path));
             continue;
         }
-        let full_path = file.get_path();
-        let relative_path = full_path
-            .strip_prefix(from_dir)
-            .map_err(CopyFileDirError::StripPathError)?
-            .to_path_buf();
 
-        let new_path = to_dir.join(relative_path);
-        let _ = std::fs::copy(full_path, new_path);
+        let new_path = to_dir.join(&relative_path);
+        let up_to_date = new_path.exists()
+            && match (file.get_hash(), lookup_hash(to_structure, &relative_path)) {
+                (Some(src_hash), Some(dst_hash)) => src_hash == dst_hash,
+                _ => false,
+            };
+        if !up_to_date {
+            ops.push(SyncOp::CopyFile {
+                from: full_path,
+                to: new_path,
+            });
+        }
     }
     for sub_dir in dir.get_dirs_as_ref() {
         let full_path = sub_dir.get_path();
-        let relative_path = full_path
-            .strip_prefix(from_dir)
-            .map_err(CopyFileDirError::StripPathError)?
-            .to_path_buf();
+        let relative_path = match full_path.strip_prefix(from_dir) {
+            Ok(r) => r.to_path_buf(),
+            Err(_) => continue,
+        };
 
         let new_path = to_dir.join(relative_path);
-        if !&new_path.exists() {
-            std::fs::create_dir(new_path).map_err(CopyFileDirError::IoError)?;
+        if !new_path.exists() {
+            ops.push(SyncOp::CreateDir(new_path));
         }
-        copy_untranslatable_files_rec(from_dir, to_dir, sub_dir)?;
+        plan_dir(from_dir, sub_dir, to_dir, to_structure, ops);
+    }
+}
+
+/// Builds `RemoveOrphan` operations for files present in `to_structure` whose relative path no
+/// longer exists anywhere under the source tree. A target file is only ever a candidate for
+/// removal if its relative path is *not* in `translatable_paths`: that list is independent of
+/// the (already rebuilt) source tree, so it still remembers a file was marked translatable even
+/// after the source copy that earned it that mark has since been deleted or renamed, and a
+/// human translation built from it must never be deleted. Orphans whose basename matches a
+/// source file that has no corresponding target entry are still removed, and are also reported
+/// as likely renames.
+///
+/// Both trees are flattened, sorted by relative path, and diffed with a merge walk rather than
+/// scanning one list per entry of the other, so this stays `O(n log n)` instead of `O(n*m)` on
+/// large trees; the rename lookup is likewise a `HashMap` built once instead of a linear scan
+/// per orphan.
+fn plan_orphans(
+    from_dir: &Path,
+    src_structure: &Directory,
+    to_dir: &Path,
+    to_structure: &Directory,
+    translatable_paths: &[PathBuf],
+    ops: &mut Vec<SyncOp>,
+    renamed: &mut Vec<(PathBuf, PathBuf)>,
+) {
+    let mut src_entries = Vec::new();
+    flatten_tree(src_structure, from_dir, &mut src_entries);
+    src_entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut target_entries = Vec::new();
+    flatten_tree(to_structure, to_dir, &mut target_entries);
+    target_entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let orphans = merge_diff_missing(&target_entries, &src_entries);
+    let new_in_source = merge_diff_missing(&src_entries, &target_entries);
+
+    let mut rename_candidates: HashMap<OsString, Vec<&PathBuf>> = HashMap::new();
+    for path in &new_in_source {
+        if let Some(name) = path.file_name() {
+            rename_candidates.entry(name.to_os_string()).or_default().push(*path);
+        }
+    }
+
+    let translatable: HashSet<&PathBuf> = translatable_paths.iter().collect();
+    for relative_path in orphans {
+        if translatable.contains(&relative_path) {
+            continue;
+        }
+        if let Some(candidate) = relative_path
+            .file_name()
+            .and_then(|name| rename_candidates.get(name))
+            .and_then(|candidates| candidates.first())
+        {
+            renamed.push((relative_path.clone(), (*candidate).clone()));
+        }
+        ops.push(SyncOp::RemoveOrphan(to_dir.join(relative_path)));
+    }
+}
+
+/// Returns the paths present in `entries` but absent from `other`, both assumed sorted by path,
+/// via a single merge walk over the two lists instead of a scan of one per entry of the other.
+fn merge_diff_missing<'a>(
+    entries: &'a [(PathBuf, bool)],
+    other: &[(PathBuf, bool)],
+) -> Vec<&'a PathBuf> {
+    let mut missing = Vec::new();
+    let mut oi = 0;
+    for (path, _) in entries {
+        while oi < other.len() && other[oi].0 < *path {
+            oi += 1;
+        }
+        let present = oi < other.len() && other[oi].0 == *path;
+        if !present {
+            missing.push(path);
+        }
+    }
+    missing
+}
+
+/// Looks up the content hash of the file at `relative_path` within `dir`, descending by path
+/// segment. Used to skip re-copying files that are already byte-identical in the target.
+fn lookup_hash<'a>(dir: &'a Directory, relative_path: &Path) -> Option<&'a String> {
+    let mut components = relative_path.components();
+    let first = components.next()?;
+    let name = first.as_os_str().to_string_lossy();
+    let rest = components.as_path();
+
+    if rest.as_os_str().is_empty() {
+        dir.get_files_as_ref()
+            .iter()
+            .find(|f| f.get_name() == name.as_ref())
+            .and_then(|f| f.get_hash())
+    } else {
+        let sub_dir = dir
+            .get_dirs_as_ref()
+            .iter()
+            .find(|d| d.get_dir_name() == name.as_ref())?;
+        lookup_hash(sub_dir, rest)
+    }
+}
+
+/// Flattens a `Directory` tree into a list of `(relative_path, translatable)` pairs.
+fn flatten_tree(dir: &Directory, root: &Path, out: &mut Vec<(PathBuf, bool)>) {
+    for file in dir.get_files_as_ref() {
+        if let Ok(relative) = file.get_path().strip_prefix(root) {
+            out.push((relative.to_path_buf(), file.is_translatable()));
+        }
+    }
+    for sub_dir in dir.get_dirs_as_ref() {
+        flatten_tree(sub_dir, root, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp directory path unique to this test run and `label`, so parallel tests never
+    /// collide on the same path.
+    fn temp_dir_path(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "translate-dir-lib-test-project-{}-{}",
+            label,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn plan_orphans_removes_orphans_and_suggests_renames_by_basename() {
+        let src_root = temp_dir_path("orphans-src");
+        let to_root = temp_dir_path("orphans-to");
+        std::fs::create_dir_all(src_root.join("notes/new")).expect("create src dirs");
+        std::fs::create_dir_all(to_root.join("notes/old")).expect("create target dirs");
+        std::fs::write(src_root.join("notes/new/report.txt"), "hello").expect("write source file");
+        std::fs::write(to_root.join("notes/old/report.txt"), "hello-old").expect("write orphan");
+        std::fs::write(to_root.join("notes/old/kept.txt"), "translation").expect("write kept");
+
+        let (src_structure, _) =
+            build_tree(&src_root, &[], FollowSymlinks::Never, None).expect("build source tree");
+        let (to_structure, _) =
+            build_tree(&to_root, &[], FollowSymlinks::Never, None).expect("build target tree");
+
+        let translatable_paths = vec![PathBuf::from("notes/old/kept.txt")];
+        let mut ops = Vec::new();
+        let mut renamed = Vec::new();
+        plan_orphans(
+            &src_root,
+            &src_structure,
+            &to_root,
+            &to_structure,
+            &translatable_paths,
+            &mut ops,
+            &mut renamed,
+        );
+
+        std::fs::remove_dir_all(&src_root).ok();
+        std::fs::remove_dir_all(&to_root).ok();
+
+        assert_eq!(
+            renamed,
+            vec![(
+                PathBuf::from("notes/old/report.txt"),
+                PathBuf::from("notes/new/report.txt")
+            )]
+        );
+        assert!(ops.iter().any(
+            |op| matches!(op, SyncOp::RemoveOrphan(p) if *p == to_root.join("notes/old/report.txt"))
+        ));
+        assert!(!ops.iter().any(
+            |op| matches!(op, SyncOp::RemoveOrphan(p) if *p == to_root.join("notes/old/kept.txt"))
+        ));
+    }
+
+    #[test]
+    fn plan_dir_creates_missing_dirs_and_copies_only_new_or_changed_files() {
+        let src_root = temp_dir_path("plandir-src");
+        let to_root = temp_dir_path("plandir-to");
+        std::fs::create_dir_all(src_root.join("sub")).expect("create src dirs");
+        std::fs::create_dir_all(&to_root).expect("create target dir");
+        std::fs::write(src_root.join("unchanged.txt"), "same").expect("write src unchanged");
+        std::fs::write(to_root.join("unchanged.txt"), "same").expect("write target unchanged");
+        std::fs::write(src_root.join("new.txt"), "new").expect("write new.txt");
+        std::fs::write(src_root.join("sub/nested.txt"), "nested").expect("write nested.txt");
+
+        let (src_structure, _) =
+            build_tree(&src_root, &[], FollowSymlinks::Never, None).expect("build source tree");
+        let (to_structure, _) =
+            build_tree(&to_root, &[], FollowSymlinks::Never, None).expect("build target tree");
+
+        let mut ops = Vec::new();
+        plan_dir(&src_root, &src_structure, &to_root, &to_structure, &mut ops);
+
+        std::fs::remove_dir_all(&src_root).ok();
+        std::fs::remove_dir_all(&to_root).ok();
+
+        assert!(ops
+            .iter()
+            .any(|op| matches!(op, SyncOp::CreateDir(p) if *p == to_root.join("sub"))));
+        assert!(ops.iter().any(|op| matches!(
+            op,
+            SyncOp::CopyFile { from, to }
+                if *to == to_root.join("new.txt") && *from == src_root.join("new.txt")
+        )));
+        assert!(ops
+            .iter()
+            .any(|op| matches!(op, SyncOp::CopyFile { to, .. } if *to == to_root.join("sub/nested.txt"))));
+        assert!(!ops
+            .iter()
+            .any(|op| matches!(op, SyncOp::CopyFile { to, .. } if *to == to_root.join("unchanged.txt"))));
     }
-    Ok(())
 }
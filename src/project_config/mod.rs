@@ -9,7 +9,27 @@ use std::{
 };
 use thiserror::Error;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+/// On-disk representation used to (de)serialize a project's [`ProjectConfig`].
+pub enum ConfigFormat {
+    /// Human-readable `trans_conf.json`, the default.
+    #[default]
+    Json,
+    /// Compact binary `trans_conf.bin`, faster to load/save for large project trees.
+    Bincode,
+}
+
+impl ConfigFormat {
+    /// The config filename this format is stored under.
+    pub(crate) fn filename(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "trans_conf.json",
+            ConfigFormat::Bincode => "trans_conf.bin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 /// A struct representing a particular project's config, this config contains the root directory
 /// structure and the
 pub struct ProjectConfig {
@@ -19,9 +39,60 @@ pub struct ProjectConfig {
     lang_dirs: Vec<LangDir>,
     /// the master directory that the files are copied and translated from
     src_dir: Option<LangDir>,
+    /// on-disk serialization backend this config is (or will be) stored with
+    #[serde(default)]
+    format: ConfigFormat,
+    /// gitignore-style glob patterns; matching files are skipped and matching directories are
+    /// never descended into when building a project tree
+    #[serde(default = "default_ignore_patterns")]
+    ignore_patterns: Vec<String>,
+    /// paths (relative to the source root) ever marked translatable, kept independent of the
+    /// live source tree so a file's "this holds a human translation" status survives the
+    /// source file itself being deleted or renamed on disk
+    #[serde(default)]
+    translatable_paths: Vec<PathBuf>,
+    /// whether `build_tree` follows symlinked directories/files instead of skipping them
+    #[serde(default)]
+    follow_symlinks: FollowSymlinks,
+    /// symlink cycles (the symlink's path) encountered the last time a project tree was built
+    #[serde(default)]
+    symlink_cycles: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+/// Whether [`build_tree`] follows symlinks it encounters while walking a project directory.
+pub enum FollowSymlinks {
+    /// Skip every symlink, as `build_tree` has always done. The default.
+    #[default]
+    Never,
+    /// Follow symlinked files and directories, guarding against cycles by tracking the
+    /// canonicalized directories currently being descended into.
+    Follow,
+}
+
+/// Ignore patterns applied to every project unless replaced with
+/// [`Project::set_ignore_patterns`]; patterns from a `.translateignore` file in the project root
+/// are added to these at init.
+fn default_ignore_patterns() -> Vec<String> {
+    vec![".git/".to_string()]
+}
+
+/// Reads gitignore-style patterns from a `.translateignore` file in `root`, skipping blank
+/// lines and `#` comments. Returns an empty list if the file does not exist.
+fn load_ignore_file(root: &Path) -> Vec<String> {
+    let contents = match std::fs::read_to_string(root.join(".translateignore")) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 /// A master directory for a language that copies the master one
 pub struct LangDir {
     dir: Directory,
@@ -45,7 +116,7 @@ impl LangDir {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 /// A config representation of a directory
 pub struct Directory {
     /// name of the directory
@@ -86,7 +157,7 @@ impl Directory {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 /// A config for a file
 pub struct File {
     /// name of the file
@@ -95,6 +166,43 @@ pub struct File {
     path: PathBuf,
     /// if the file is translatable (false is not, true if it is)
     translatable: bool,
+    /// content digest of the file as of the last time the tree was built, used to skip
+    /// re-copying byte-identical files and to detect source edits
+    hash: Option<String>,
+    /// content digest of this file at the moment it was marked translatable; compared against
+    /// `hash` on later builds to tell whether the source has changed since translation
+    translated_source_hash: Option<String>,
+    /// true if `hash` no longer matches `translated_source_hash`, i.e. the source has changed
+    /// since this file was marked translatable
+    #[serde(default)]
+    stale: bool,
+    /// mtime reported by the filesystem as of the last time this file was hashed; lets
+    /// `build_tree` skip rehashing a file whose mtime hasn't moved since the prior build
+    #[serde(default, with = "system_time_serde")]
+    modified: Option<std::time::SystemTime>,
+}
+
+/// `std::time::SystemTime` has no `serde` impl of its own, so `File::modified` is (de)serialized
+/// through this as nanoseconds since the Unix epoch.
+mod system_time_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub(super) fn serialize<S>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let nanos = time.map(|t| t.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_nanos() as u64);
+        nanos.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let nanos: Option<u64> = Option::deserialize(deserializer)?;
+        Ok(nanos.map(|n| UNIX_EPOCH + Duration::from_nanos(n)))
+    }
 }
 
 impl File {
@@ -107,19 +215,88 @@ impl File {
     pub(crate) fn is_translatable(&self) -> bool {
         self.translatable
     }
+    pub(crate) fn get_hash(&self) -> Option<&String> {
+        self.hash.as_ref()
+    }
+    pub(crate) fn is_stale(&self) -> bool {
+        self.stale
+    }
+}
+
+/// Computes a content digest for the file at `path`, used to detect changed content without
+/// relying on modification times alone. This digest is persisted to disk and compared across
+/// process runs (and toolchain upgrades), so it has to be stable in a way
+/// `std::collections::hash_map::DefaultHasher` explicitly is not (its docs disclaim any
+/// guarantee that the same input hashes the same way across releases, or even compilations).
+/// CRC-32 is hand-rolled here rather than pulled in from a crate because this crate has none
+/// available for it.
+fn hash_file_contents(path: &Path) -> std::io::Result<String> {
+    let contents = std::fs::read(path)?;
+    Ok(format!("{:08x}{:x}", crc32(&contents), contents.len()))
+}
+
+/// CRC-32 (IEEE 802.3 polynomial, reflected form), computed a bit at a time rather than via a
+/// precomputed 256-entry lookup table: this isn't on a hot path, and a table would be the kind
+/// of premature optimization this codebase avoids elsewhere.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
 }
 
 impl ProjectConfig {
-    fn new(proj_name: &str) -> Self {
+    fn new(proj_name: &str, format: ConfigFormat) -> Self {
         ProjectConfig {
             name: proj_name.to_string(),
             lang_dirs: Vec::new(),
             src_dir: None,
+            format,
+            ignore_patterns: default_ignore_patterns(),
+            translatable_paths: Vec::new(),
+            follow_symlinks: FollowSymlinks::Never,
+            symlink_cycles: Vec::new(),
         }
     }
     pub(crate) fn get_name(&self) -> String {
         self.name.clone()
     }
+    pub fn get_format(&self) -> ConfigFormat {
+        self.format
+    }
+    pub(crate) fn get_ignore_patterns_as_ref(&self) -> &Vec<String> {
+        &self.ignore_patterns
+    }
+    /// Replaces the ignore patterns used when building project trees. Callers must re-analyze
+    /// (e.g. via [`ProjectConfig::refresh_src_dir`] and [`ProjectConfig::analyze_lang_dirs`])
+    /// for the change to take effect.
+    pub fn set_ignore_patterns(&mut self, patterns: Vec<String>) {
+        self.ignore_patterns = patterns;
+    }
+    /// Paths (relative to the source root) ever marked translatable. Unlike the live source
+    /// tree, this survives the corresponding source file being deleted or renamed, so callers
+    /// can tell a human translation apart from an orphaned plain copy even after that happens.
+    pub(crate) fn get_translatable_paths_as_ref(&self) -> &Vec<PathBuf> {
+        &self.translatable_paths
+    }
+    pub fn get_follow_symlinks(&self) -> FollowSymlinks {
+        self.follow_symlinks
+    }
+    /// Changes whether `build_tree` follows symlinks. Callers must re-analyze for the change
+    /// to take effect, same as [`ProjectConfig::set_ignore_patterns`].
+    pub fn set_follow_symlinks(&mut self, mode: FollowSymlinks) {
+        self.follow_symlinks = mode;
+    }
+    /// Symlink cycles encountered the last time a project tree was built, as the path of the
+    /// symlink that would have caused the cycle.
+    pub fn get_symlink_cycles(&self) -> &Vec<PathBuf> {
+        &self.symlink_cycles
+    }
     pub(crate) fn get_src_dir_as_ref(&self) -> &Option<LangDir> {
         &self.src_dir
     }
@@ -127,34 +304,96 @@ impl ProjectConfig {
         &self.lang_dirs
     }
     pub(crate) fn set_src_dir(&mut self, dir_path: PathBuf, lang: Language) -> std::io::Result<()> {
-        let dir = build_tree(dir_path)?;
+        let (dir, cycles) = build_tree(&dir_path, &self.ignore_patterns, self.follow_symlinks, None)?;
+        self.replace_symlink_cycles_under(&dir_path, cycles);
         let lang_dir = LangDir::new(dir, lang);
 
         self.src_dir = Some(lang_dir);
         Ok(())
     }
     pub(crate) fn add_lang(&mut self, dir_path: PathBuf, lang: Language) -> std::io::Result<()> {
-        let dir = build_tree(dir_path)?;
+        let (dir, cycles) = build_tree(&dir_path, &self.ignore_patterns, self.follow_symlinks, None)?;
+        self.replace_symlink_cycles_under(&dir_path, cycles);
         let lang_dir = LangDir::new(dir, lang);
         self.lang_dirs.push(lang_dir);
         Ok(())
     }
     pub(crate) fn analyze_lang_dirs(&mut self) -> std::io::Result<()> {
+        let ignore_patterns = self.ignore_patterns.clone();
+        let follow_symlinks = self.follow_symlinks;
         for dir in &mut self.lang_dirs {
             let path = dir.get_dir_as_ref().get_path();
-            let tree = build_tree(path)?;
+            let (tree, cycles) =
+                build_tree(&path, &ignore_patterns, follow_symlinks, Some(dir.get_dir_as_ref()))?;
             dir.set_dir(tree);
+            self.symlink_cycles
+                .retain(|cycle| !cycle.starts_with(&path));
+            self.symlink_cycles.extend(cycles);
         }
         Ok(())
     }
 
+    /// Rebuilds the source directory tree from disk, recomputing content hashes (save for files
+    /// whose mtime hasn't changed since the prior build, which reuse their previous hash) while
+    /// preserving each file's `translatable` flag and translated-source-hash snapshot so
+    /// staleness can be recomputed.
+    pub(crate) fn refresh_src_dir(&mut self) -> std::io::Result<()> {
+        let ignore_patterns = self.ignore_patterns.clone();
+        let follow_symlinks = self.follow_symlinks;
+        let src = match &mut self.src_dir {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+        let path = src.get_dir_as_ref().get_path();
+        let (mut tree, cycles) = build_tree(
+            &path,
+            &ignore_patterns,
+            follow_symlinks,
+            Some(src.get_dir_as_ref()),
+        )?;
+        merge_file_state(src.get_dir_as_ref(), &mut tree);
+        src.set_dir(tree);
+        self.replace_symlink_cycles_under(&path, cycles);
+        self.backfill_translatable_paths();
+        Ok(())
+    }
+
+    /// Drops any previously recorded symlink cycle found under `root` and replaces it with
+    /// `cycles`, the ones found by the build that just ran. Scoped to `root` rather than a
+    /// blanket clear so rebuilding one tree (e.g. the source dir) doesn't discard cycles still
+    /// recorded for another (e.g. a language dir) that wasn't part of this rebuild.
+    fn replace_symlink_cycles_under(&mut self, root: &Path, cycles: Vec<PathBuf>) {
+        self.symlink_cycles.retain(|cycle| !cycle.starts_with(root));
+        self.symlink_cycles.extend(cycles);
+    }
+
+    /// Folds every `translatable == true` file currently in the source tree into
+    /// `translatable_paths`. Self-healing safety net for configs that predate that field, or
+    /// where it otherwise drifted out of sync with the live tree.
+    fn backfill_translatable_paths(&mut self) {
+        let src = match &self.src_dir {
+            Some(r) => r,
+            None => return,
+        };
+        let root = src.get_dir_as_ref().get_path();
+        let mut found = Vec::new();
+        collect_translatable_paths(src.get_dir_as_ref(), &root, &mut found);
+        for relative in found {
+            if !self.translatable_paths.contains(&relative) {
+                self.translatable_paths.push(relative);
+            }
+        }
+    }
+
     pub fn make_translatable_file(
         &mut self,
         path: PathBuf,
     ) -> Result<(), AddTranslatableFileError> {
-        let mut func = |f: &mut File| {
-            f.translatable = true;
+        let root = match &self.src_dir {
+            Some(r) => r.get_dir_as_ref().get_path(),
+            None => return Err(AddTranslatableFileError::NoSourceLang),
         };
+        let mut func = mark_translatable(true);
         let src_dir = &mut match &mut self.src_dir {
             Some(r) => r,
             None => {
@@ -162,20 +401,27 @@ impl ProjectConfig {
             }
         }
         .dir;
-        let res = find_file_and_apply(src_dir, &path, &mut func);
-        match res {
-            true => Ok(()),
-            false => Err(AddTranslatableFileError::NoFile),
+        if !find_file_and_apply(src_dir, &path, &mut func) {
+            return Err(AddTranslatableFileError::NoFile);
+        }
+        if let Ok(relative) = path.strip_prefix(&root) {
+            let relative = relative.to_path_buf();
+            if !self.translatable_paths.contains(&relative) {
+                self.translatable_paths.push(relative);
+            }
         }
+        Ok(())
     }
 
     pub fn make_untranslatable_file(
         &mut self,
         path: PathBuf,
     ) -> Result<(), AddTranslatableFileError> {
-        let mut func = |f: &mut File| {
-            f.translatable = false;
+        let root = match &self.src_dir {
+            Some(r) => r.get_dir_as_ref().get_path(),
+            None => return Err(AddTranslatableFileError::NoSourceLang),
         };
+        let mut func = mark_translatable(false);
         let src_dir = &mut match &mut self.src_dir {
             Some(r) => r,
             None => {
@@ -183,11 +429,113 @@ impl ProjectConfig {
             }
         }
         .dir;
-        let res = find_file_and_apply(src_dir, &path, &mut func);
-        match res {
-            true => Ok(()),
-            false => Err(AddTranslatableFileError::NoFile),
+        if !find_file_and_apply(src_dir, &path, &mut func) {
+            return Err(AddTranslatableFileError::NoFile);
+        }
+        if let Ok(relative) = path.strip_prefix(&root) {
+            self.translatable_paths.retain(|p| p != relative);
+        }
+        Ok(())
+    }
+
+    /// Makes every file in the source directory whose path (relative to the source root)
+    /// matches `pattern` translatable, returning how many files matched (including any that
+    /// were already translatable).
+    ///
+    /// Supports the usual glob syntax: `*` and `?` within a path segment, `**` to match
+    /// across segments, and `[...]` character classes.
+    pub fn make_translatable_glob(&mut self, pattern: &str) -> Result<usize, AddTranslatableFileError> {
+        self.apply_glob(pattern, true)
+    }
+
+    /// Makes every file in the source directory whose path (relative to the source root)
+    /// matches `pattern` untranslatable, returning how many files matched (including any that
+    /// were already untranslatable).
+    pub fn make_untranslatable_glob(&mut self, pattern: &str) -> Result<usize, AddTranslatableFileError> {
+        self.apply_glob(pattern, false)
+    }
+
+    /// Makes every file under `dir_path` (relative to the source root) translatable, as if
+    /// `dir_path/**/*` had been passed to [`ProjectConfig::make_translatable_glob`].
+    pub fn make_directory_translatable(&mut self, dir_path: &Path) -> Result<usize, AddTranslatableFileError> {
+        let pattern = format!("{}/**/*", dir_path.to_string_lossy().trim_end_matches('/'));
+        self.apply_glob(&pattern, true)
+    }
+
+    fn apply_glob(&mut self, pattern: &str, translatable: bool) -> Result<usize, AddTranslatableFileError> {
+        let src = match &mut self.src_dir {
+            Some(r) => r,
+            None => {
+                return Err(AddTranslatableFileError::NoSourceLang);
+            }
+        };
+        let root = src.get_dir_as_ref().get_path();
+        let mut pred = |relative: &Path| glob_match(pattern, relative);
+        let mut func = mark_translatable(translatable);
+        let mut matched = Vec::new();
+        apply_to_matching(&mut src.dir, &root, &mut pred, &mut func, &mut matched);
+        if matched.is_empty() {
+            return Err(AddTranslatableFileError::NoFile);
         }
+        if translatable {
+            for relative in &matched {
+                if !self.translatable_paths.contains(relative) {
+                    self.translatable_paths.push(relative.clone());
+                }
+            }
+        } else {
+            self.translatable_paths.retain(|p| !matched.contains(p));
+        }
+        Ok(matched.len())
+    }
+}
+
+/// Returns a mutator that flips `File::translatable` and keeps the staleness snapshot
+/// consistent: marking a file translatable snapshots its current hash, and marking it
+/// untranslatable clears the snapshot since staleness no longer applies.
+fn mark_translatable(translatable: bool) -> impl FnMut(&mut File) {
+    move |f: &mut File| {
+        f.translatable = translatable;
+        f.translated_source_hash = if translatable { f.hash.clone() } else { None };
+        f.stale = false;
+    }
+}
+
+/// Copies `translatable` and `translated_source_hash` from `old` onto the matching files of
+/// `new` (matched by name within the same parent directory) and recomputes `stale` from the
+/// freshly rebuilt `hash`. Used by [`ProjectConfig::refresh_src_dir`] (and by
+/// [`crate::project::Project::plan_sync`] for its own in-memory rebuild) so a tree rebuild
+/// doesn't forget which files were marked translatable or when they were last translated.
+pub(crate) fn merge_file_state(old: &Directory, new: &mut Directory) {
+    for file in &mut new.files {
+        if let Some(old_file) = old.files.iter().find(|f| f.name == file.name) {
+            file.translatable = old_file.translatable;
+            file.translated_source_hash = old_file.translated_source_hash.clone();
+            file.stale = file.translatable
+                && match (&file.hash, &file.translated_source_hash) {
+                    (Some(current), Some(snapshot)) => current != snapshot,
+                    _ => false,
+                };
+        }
+    }
+    for sub_dir in &mut new.dirs {
+        if let Some(old_dir) = old.dirs.iter().find(|d| d.name == sub_dir.name) {
+            merge_file_state(old_dir, sub_dir);
+        }
+    }
+}
+
+/// Collects the paths (relative to `root`) of every `translatable == true` file under `dir`.
+fn collect_translatable_paths(dir: &Directory, root: &Path, out: &mut Vec<PathBuf>) {
+    for file in &dir.files {
+        if file.translatable {
+            if let Ok(relative) = file.path.strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+        }
+    }
+    for sub_dir in &dir.dirs {
+        collect_translatable_paths(sub_dir, root, out);
     }
 }
 
@@ -211,32 +559,288 @@ where
     false
 }
 
-/// Build a `Directory` tree rooted at `root`.
-pub fn build_tree<P: AsRef<Path>>(root: P) -> std::io::Result<Directory> {
-    fn recurse(path: &Path) -> std::io::Result<Directory> {
-        let name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().into_owned())
-            .unwrap_or_else(|| String::from("/"));
+/// Recursively applies `func` to every file under `dir` whose path, relative to `root`,
+/// satisfies `pred`, pushing each matched file's relative path onto `matched`.
+fn apply_to_matching<F, P>(
+    dir: &mut Directory,
+    root: &Path,
+    pred: &mut P,
+    func: &mut F,
+    matched: &mut Vec<PathBuf>,
+) where
+    F: FnMut(&mut File),
+    P: FnMut(&Path) -> bool,
+{
+    for file in &mut dir.files {
+        let relative = match file.get_path().strip_prefix(root) {
+            Ok(r) => r.to_path_buf(),
+            Err(_) => continue,
+        };
+        if pred(&relative) {
+            (func)(file);
+            matched.push(relative);
+        }
+    }
+    for sub_dir in &mut dir.dirs {
+        apply_to_matching(sub_dir, root, pred, func, matched);
+    }
+}
+
+/// Matches `path` against a gitignore/glob-style `pattern`.
+///
+/// `*` matches any run of characters within a single path segment, `?` matches a single
+/// character, `**` matches any number of segments (including none), and `[...]` matches one
+/// of a set or range of characters (e.g. `[a-z]`, `[!0-9]`).
+fn glob_match(pattern: &str, path: &Path) -> bool {
+    let path_str = path.to_string_lossy().replace('\\', "/");
+    let pattern_segments: Vec<&str> = pattern.split('/').collect();
+    let path_segments: Vec<&str> = path_str.split('/').collect();
+    match_segments(&pattern_segments, &path_segments)
+}
+
+/// Matches `pattern` against `path`, segment by segment. Backtracking on `**` (try matching it
+/// against every possible number of segments) is memoized on `(pattern_index, path_index)` so a
+/// pattern with several `**`s stays `O(pattern.len() * path.len()^2)` instead of exponential.
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    let mut cache = vec![vec![None; path.len() + 1]; pattern.len() + 1];
+    match_segments_memo(pattern, path, 0, 0, &mut cache)
+}
+
+fn match_segments_memo(
+    pattern: &[&str],
+    path: &[&str],
+    pi: usize,
+    pj: usize,
+    cache: &mut [Vec<Option<bool>>],
+) -> bool {
+    if let Some(cached) = cache[pi][pj] {
+        return cached;
+    }
+    let result = match pattern.get(pi) {
+        None => pj == path.len(),
+        Some(&"**") => {
+            if pi == pattern.len() - 1 {
+                true
+            } else {
+                (pj..=path.len()).any(|skip| match_segments_memo(pattern, path, pi + 1, skip, cache))
+            }
+        }
+        Some(seg) => match path.get(pj) {
+            Some(first) => {
+                match_segment(seg, first) && match_segments_memo(pattern, path, pi + 1, pj + 1, cache)
+            }
+            None => false,
+        },
+    };
+    cache[pi][pj] = Some(result);
+    result
+}
+
+/// A single logical unit of a pattern segment, pre-split so character classes (which span
+/// several characters of the raw pattern) are matched against one path character at a time,
+/// same as every other token.
+enum PatternToken {
+    /// `*`: any run of characters, including none.
+    Star,
+    /// `?`: exactly one character.
+    Question,
+    /// `[...]`/`[!...]`/`[^...]`: one character from (or, if negated, outside) a class.
+    Class { chars: Vec<char>, negate: bool },
+    /// Any other character, matched literally.
+    Literal(char),
+}
+
+/// Splits a pattern segment into [`PatternToken`]s. A `[` with no matching `]` is treated as a
+/// literal character.
+fn tokenize_segment(pattern: &[char]) -> Vec<PatternToken> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            '*' => {
+                tokens.push(PatternToken::Star);
+                i += 1;
+            }
+            '?' => {
+                tokens.push(PatternToken::Question);
+                i += 1;
+            }
+            '[' => match pattern[i..].iter().position(|&c| c == ']') {
+                Some(offset) => {
+                    let close = i + offset;
+                    let negate = pattern.get(i + 1) == Some(&'!') || pattern.get(i + 1) == Some(&'^');
+                    let class_start = i + if negate { 2 } else { 1 };
+                    tokens.push(PatternToken::Class {
+                        chars: pattern[class_start..close].to_vec(),
+                        negate,
+                    });
+                    i = close + 1;
+                }
+                None => {
+                    tokens.push(PatternToken::Literal('['));
+                    i += 1;
+                }
+            },
+            c => {
+                tokens.push(PatternToken::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Matches a single path segment against a single pattern segment containing `*`, `?` and
+/// `[...]` character classes.
+fn match_segment(pattern: &str, segment: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let tokens = tokenize_segment(&p);
+    let s: Vec<char> = segment.chars().collect();
+    match_chars(&tokens, &s)
+}
+
+/// Classic `O(tokens.len() * chars.len())` wildcard-matching table, filled bottom-up so each
+/// `(i, j)` suffix is computed once and adversarial patterns like `"*a*a*a*a*a*b"` stay
+/// polynomial.
+fn match_chars(tokens: &[PatternToken], s: &[char]) -> bool {
+    let t_len = tokens.len();
+    let s_len = s.len();
+    let mut dp = vec![vec![false; s_len + 1]; t_len + 1];
+    dp[t_len][s_len] = true;
+
+    for i in (0..t_len).rev() {
+        for j in (0..=s_len).rev() {
+            dp[i][j] = match &tokens[i] {
+                PatternToken::Star => dp[i + 1][j] || (j < s_len && dp[i][j + 1]),
+                PatternToken::Question => j < s_len && dp[i + 1][j + 1],
+                PatternToken::Class { chars, negate } => {
+                    j < s_len && (char_in_class(chars, s[j]) != *negate) && dp[i + 1][j + 1]
+                }
+                PatternToken::Literal(c) => j < s_len && s[j] == *c && dp[i + 1][j + 1],
+            };
+        }
+    }
+    dp[0][0]
+}
 
+fn char_in_class(class: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if c >= class[i] && c <= class[i + 2] {
+                return true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+/// Build a `Directory` tree rooted at `root`, returning the tree alongside any symlink cycles
+/// detected while following links (empty unless `follow_symlinks` is `Follow`).
+///
+/// `prior`, when given the tree this same root was built into last time, lets a file whose
+/// mtime hasn't changed since then reuse its previous hash instead of being re-read and
+/// re-hashed from disk — the whole point of tracking `File::modified` in the first place, since
+/// rehashing every file on every build scales badly on large project trees.
+pub fn build_tree<P: AsRef<Path>>(
+    root: P,
+    ignore_patterns: &[String],
+    follow_symlinks: FollowSymlinks,
+    prior: Option<&Directory>,
+) -> std::io::Result<(Directory, Vec<PathBuf>)> {
+    fn recurse(
+        path: &Path,
+        root: &Path,
+        ignore_patterns: &[String],
+        follow_symlinks: FollowSymlinks,
+        stack: &mut Vec<PathBuf>,
+        cycles: &mut Vec<PathBuf>,
+        prior: Option<&Directory>,
+    ) -> std::io::Result<Directory> {
         let mut dir = Directory::new(path.to_path_buf());
 
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
             let meta = entry.metadata()?;
+            let entry_path = entry.path();
+            let is_symlink = meta.is_symlink();
+
+            if is_symlink && follow_symlinks == FollowSymlinks::Never {
+                continue;
+            }
+
+            // For a symlink, everything below (ignore matching, file/dir dispatch, cycle
+            // detection) is decided by what it points at, not the link itself.
+            let real_meta = if is_symlink {
+                match std::fs::metadata(&entry_path) {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                }
+            } else {
+                meta
+            };
 
-            if meta.is_symlink() {
+            let relative = entry_path.strip_prefix(root).unwrap_or(&entry_path);
+            if is_ignored(ignore_patterns, relative, real_meta.is_dir()) {
                 continue;
             }
 
-            if meta.is_dir() {
-                dir.dirs.push(recurse(&entry.path())?);
-            } else if meta.is_file() {
+            if real_meta.is_dir() {
+                // `stack` holds the canonicalized path of every directory currently being
+                // descended into, whether reached by plain recursion or by following a
+                // symlink, so a symlink pointing at *any* ancestor on the current descent
+                // path is recognized as a cycle the moment it is encountered.
+                let canonical = match std::fs::canonicalize(&entry_path) {
+                    Ok(c) => c,
+                    Err(_) => continue,
+                };
+                if stack.contains(&canonical) {
+                    if is_symlink {
+                        cycles.push(entry_path);
+                    }
+                    continue;
+                }
+                let dir_name = entry.file_name().to_string_lossy().into_owned();
+                let prior_sub = prior.and_then(|p| p.dirs.iter().find(|d| d.name == dir_name));
+                stack.push(canonical);
+                let sub = recurse(
+                    &entry_path,
+                    root,
+                    ignore_patterns,
+                    follow_symlinks,
+                    stack,
+                    cycles,
+                    prior_sub,
+                )?;
+                stack.pop();
+                dir.dirs.push(sub);
+            } else if real_meta.is_file() {
                 let file_name = entry.file_name().to_string_lossy().into_owned();
+                let modified = real_meta.modified().ok();
+                let prior_file = prior.and_then(|p| p.files.iter().find(|f| f.name == file_name));
+                let reuse_hash = match (modified, prior_file) {
+                    (Some(m), Some(f)) => f.hash.is_some() && f.modified == Some(m),
+                    _ => false,
+                };
+                let hash = if reuse_hash {
+                    prior_file.and_then(|f| f.hash.clone())
+                } else {
+                    hash_file_contents(&entry_path).ok()
+                };
                 dir.files.push(File {
-                    name: file_name.clone(),
-                    path: entry.path(),
+                    name: file_name,
+                    path: entry_path,
                     translatable: false,
+                    hash,
+                    translated_source_hash: None,
+                    stale: false,
+                    modified,
                 });
             }
         }
@@ -244,21 +848,73 @@ pub fn build_tree<P: AsRef<Path>>(root: P) -> std::io::Result<Directory> {
         Ok(dir)
     }
 
-    recurse(root.as_ref())
+    let root = root.as_ref();
+    let mut stack = Vec::new();
+    if let Ok(canonical_root) = std::fs::canonicalize(root) {
+        stack.push(canonical_root);
+    }
+    let mut cycles = Vec::new();
+    let dir = recurse(
+        root,
+        root,
+        ignore_patterns,
+        follow_symlinks,
+        &mut stack,
+        &mut cycles,
+        prior,
+    )?;
+    Ok((dir, cycles))
 }
 
-/// Init project config with it's file
-pub fn init(proj_name: &str, path: PathBuf) -> Result<(), InitProjectError> {
+/// Returns true if `relative` (a path relative to the tree root) should be skipped: a pattern
+/// ending in `/` only matches directories, a pattern containing `/` is matched against the
+/// full relative path, and a bare pattern is matched against the entry's basename at any depth.
+fn is_ignored(patterns: &[String], relative: &Path, is_dir: bool) -> bool {
+    let relative_str = relative.to_string_lossy().replace('\\', "/");
+    for pattern in patterns {
+        let (pattern, dir_only) = match pattern.strip_suffix('/') {
+            Some(stripped) => (stripped, true),
+            None => (pattern.as_str(), false),
+        };
+        if dir_only && !is_dir {
+            continue;
+        }
+        let matched = if pattern.contains('/') {
+            glob_match(pattern, Path::new(&relative_str))
+        } else {
+            relative
+                .file_name()
+                .map(|name| match_segment(pattern, &name.to_string_lossy()))
+                .unwrap_or(false)
+        };
+        if matched {
+            return true;
+        }
+    }
+    false
+}
+
+/// Init project config with it's file, stored on disk using `format`
+pub fn init(proj_name: &str, path: PathBuf, format: ConfigFormat) -> Result<(), InitProjectError> {
     if !path.exists() {
         return Err(InitProjectError::InvalidPath);
     }
-    let config_filename = "trans_conf.json";
-    let config_file_fullpath = path.join(config_filename);
-    if config_file_fullpath.exists() {
+    // Check every known format's filename, not just the one being initialized, so a project
+    // already initialized with e.g. `Json` can't be re-initialized with `Bincode` into a second,
+    // conflicting config file.
+    let already_initialized = [ConfigFormat::Json, ConfigFormat::Bincode]
+        .iter()
+        .any(|f| path.join(f.filename()).exists());
+    if already_initialized {
         return Err(InitProjectError::ProjectAlreadyInitialized);
     }
+    let config_file_fullpath = path.join(format.filename());
 
-    let conf = ProjectConfig::new(proj_name);
+    let mut conf = ProjectConfig::new(proj_name, format);
+    let extra_patterns = load_ignore_file(&path);
+    if !extra_patterns.is_empty() {
+        conf.ignore_patterns.extend(extra_patterns);
+    }
     let _ = write_conf(config_file_fullpath, &conf).map_err(InitProjectError::ConfigWritingError);
     Ok(())
 }
@@ -272,29 +928,212 @@ pub(crate) fn write_conf(path: PathBuf, conf: &ProjectConfig) -> Result<(), Writ
         .open(path)
         .map_err(WriteConfigError::IoError)?;
 
-    let serialized = serde_json::to_string(conf)
-        .map_err(|e| WriteConfigError::SerialisationError(e.to_string()))?;
-    file.write_fmt(format_args!("{}", serialized))
-        .map_err(WriteConfigError::IoError)?;
+    match conf.format {
+        ConfigFormat::Json => {
+            let serialized = serde_json::to_string(conf)
+                .map_err(|e| WriteConfigError::SerialisationError(e.to_string()))?;
+            file.write_all(serialized.as_bytes())
+                .map_err(WriteConfigError::IoError)?;
+        }
+        ConfigFormat::Bincode => {
+            let serialized = bincode::serialize(conf)
+                .map_err(|e| WriteConfigError::SerialisationError(e.to_string()))?;
+            file.write_all(&serialized).map_err(WriteConfigError::IoError)?;
+        }
+    }
     Ok(())
 }
 
+/// Loads a `ProjectConfig` from `path`, sniffing whether it was written as JSON or bincode so
+/// projects initialized before [`ConfigFormat`] existed keep loading unchanged.
 pub fn load_config_from_file(path: PathBuf) -> Result<ProjectConfig, LoadConfigError> {
     let mut conf_file = std::fs::OpenOptions::new()
         .read(true)
         .open(&path)
         .map_err(LoadConfigError::OpenConfigFileError)?;
-    let mut contents = String::new();
+    let mut contents = Vec::new();
     let _ = conf_file
-        .read_to_string(&mut contents)
+        .read_to_end(&mut contents)
         .map_err(LoadConfigError::OpenConfigFileError)?;
-    let conf: ProjectConfig = serde_json::from_str(contents.as_str())
-        .map_err(|_| LoadConfigError::IncorrectConfigFileFormat)?;
 
-    Ok(conf)
+    if let Ok(text) = std::str::from_utf8(&contents) {
+        if let Ok(conf) = serde_json::from_str::<ProjectConfig>(text) {
+            return Ok(conf);
+        }
+    }
+    bincode::deserialize::<ProjectConfig>(&contents)
+        .map_err(|_| LoadConfigError::IncorrectConfigFileFormat)
 }
 
 // commands
 //pub fn add_lang_dir(dir_name: &str, lang: Language) -> Result<(), Box<dyn std::error::Error>> {
 //    todo!()
 //}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp directory path unique to this test run and `label`, so parallel tests never
+    /// collide on the same path.
+    fn temp_dir_path(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "translate-dir-lib-test-{}-{}",
+            label,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn glob_match_supports_double_star_question_mark_and_character_classes() {
+        assert!(glob_match("**/*.md", Path::new("docs/guide/readme.md")));
+        assert!(glob_match("*.md", Path::new("readme.md")));
+        assert!(!glob_match("*.md", Path::new("docs/readme.md")));
+        assert!(glob_match("file?.txt", Path::new("file1.txt")));
+        assert!(!glob_match("file?.txt", Path::new("file10.txt")));
+        assert!(glob_match("[a-c]*.txt", Path::new("b-report.txt")));
+        assert!(!glob_match("[a-c]*.txt", Path::new("d-report.txt")));
+        assert!(glob_match("[!a-c]*.txt", Path::new("d-report.txt")));
+    }
+
+    #[test]
+    fn apply_to_matching_flips_only_files_under_the_glob_and_reports_matches() {
+        let dir = temp_dir_path("glob-apply");
+        std::fs::create_dir_all(dir.join("docs")).expect("create temp dir");
+        std::fs::write(dir.join("docs/a.md"), "a").expect("write a.md");
+        std::fs::write(dir.join("docs/b.txt"), "b").expect("write b.txt");
+
+        let (mut tree, _cycles) =
+            build_tree(&dir, &[], FollowSymlinks::Never, None).expect("build tree");
+        let mut pred = |relative: &Path| glob_match("docs/**/*.md", relative);
+        let mut func = mark_translatable(true);
+        let mut matched = Vec::new();
+        apply_to_matching(&mut tree, &dir, &mut pred, &mut func, &mut matched);
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(matched, vec![PathBuf::from("docs/a.md")]);
+        let docs = tree.dirs.iter().find(|d| d.name == "docs").unwrap();
+        let md_file = docs.files.iter().find(|f| f.name == "a.md").unwrap();
+        assert!(md_file.translatable);
+        let txt_file = docs.files.iter().find(|f| f.name == "b.txt").unwrap();
+        assert!(!txt_file.translatable);
+    }
+
+    #[test]
+    fn merge_file_state_marks_a_translatable_file_stale_once_its_source_changes() {
+        let dir = temp_dir_path("merge-state-stale");
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let file_path = dir.join("a.txt");
+        std::fs::write(&file_path, "v1").expect("write v1");
+
+        let (mut old_tree, _) =
+            build_tree(&dir, &[], FollowSymlinks::Never, None).expect("build tree v1");
+        mark_translatable(true)(&mut old_tree.files[0]);
+
+        std::fs::write(&file_path, "v2").expect("write v2");
+        let (mut new_tree, _) =
+            build_tree(&dir, &[], FollowSymlinks::Never, None).expect("build tree v2");
+        merge_file_state(&old_tree, &mut new_tree);
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(new_tree.files[0].translatable);
+        assert!(new_tree.files[0].stale);
+    }
+
+    #[test]
+    fn merge_file_state_leaves_an_unchanged_translatable_file_not_stale() {
+        let dir = temp_dir_path("merge-state-fresh");
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let file_path = dir.join("a.txt");
+        std::fs::write(&file_path, "v1").expect("write v1");
+
+        let (mut old_tree, _) =
+            build_tree(&dir, &[], FollowSymlinks::Never, None).expect("build tree v1");
+        mark_translatable(true)(&mut old_tree.files[0]);
+
+        let (mut new_tree, _) =
+            build_tree(&dir, &[], FollowSymlinks::Never, None).expect("build tree again");
+        merge_file_state(&old_tree, &mut new_tree);
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(new_tree.files[0].translatable);
+        assert!(!new_tree.files[0].stale);
+    }
+
+    #[test]
+    fn build_tree_prunes_directories_and_files_matching_ignore_patterns() {
+        let dir = temp_dir_path("ignore-patterns");
+        std::fs::create_dir_all(dir.join("target")).expect("create target dir");
+        std::fs::write(dir.join("target/bin.exe"), "x").expect("write target/bin.exe");
+        std::fs::write(dir.join("keep.txt"), "x").expect("write keep.txt");
+        std::fs::write(dir.join("skip.tmp"), "x").expect("write skip.tmp");
+
+        let patterns = vec!["target/".to_string(), "*.tmp".to_string()];
+        let (tree, _cycles) =
+            build_tree(&dir, &patterns, FollowSymlinks::Never, None).expect("build tree");
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(
+            tree.dirs.is_empty(),
+            "a directory matched by a `/`-suffixed pattern should be pruned entirely, not just its contents"
+        );
+        assert_eq!(tree.files.len(), 1);
+        assert_eq!(tree.files[0].name, "keep.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_tree_follow_detects_a_symlink_cycle_back_to_an_ancestor() {
+        let dir = temp_dir_path("symlink-cycle");
+        std::fs::create_dir_all(dir.join("a/b")).expect("create nested dirs");
+        let loop_path = dir.join("a/b/loop");
+        std::os::unix::fs::symlink(&dir, &loop_path).expect("create symlink back to root");
+
+        let (_tree, cycles) =
+            build_tree(&dir, &[], FollowSymlinks::Follow, None).expect("build tree");
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(cycles, vec![loop_path]);
+    }
+
+    #[test]
+    fn config_round_trips_through_json_and_bincode() {
+        let mut conf = ProjectConfig::new("demo", ConfigFormat::Json);
+        conf.ignore_patterns.push("*.tmp".to_string());
+        conf.translatable_paths.push(PathBuf::from("docs/readme.md"));
+        conf.follow_symlinks = FollowSymlinks::Follow;
+
+        let json = serde_json::to_string(&conf).expect("serialize as json");
+        let from_json: ProjectConfig = serde_json::from_str(&json).expect("deserialize from json");
+        assert_eq!(conf, from_json);
+
+        let encoded = bincode::serialize(&conf).expect("serialize as bincode");
+        let from_bincode: ProjectConfig =
+            bincode::deserialize(&encoded).expect("deserialize from bincode");
+        assert_eq!(conf, from_bincode);
+    }
+
+    #[test]
+    fn init_rejects_a_second_format_for_the_same_project() {
+        let dir = std::env::temp_dir().join(format!(
+            "translate-dir-lib-test-init-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp project dir");
+
+        init("demo", dir.clone(), ConfigFormat::Json).expect("first init succeeds");
+        let result = init("demo", dir.clone(), ConfigFormat::Bincode);
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(matches!(
+            result,
+            Err(InitProjectError::ProjectAlreadyInitialized)
+        ));
+    }
+}